@@ -0,0 +1,101 @@
+use std::cmp::min;
+use std::collections::HashMap;
+
+/// A metric tree keyed by Levenshtein edit distance, used to find indexed
+/// terms within a given edit distance of a (possibly misspelled) query term.
+///
+/// Invariant: no two children of the same node share an edge distance, since
+/// a node's children are keyed by that distance.
+pub struct BkTree {
+    root: Option<Box<Node>>,
+}
+
+struct Node {
+    term: String,
+    children: HashMap<usize, Node>,
+}
+
+impl BkTree {
+    pub fn new() -> Self {
+        BkTree { root: None }
+    }
+
+    pub fn insert(&mut self, term: String) {
+        match &mut self.root {
+            Some(root) => root.insert(term),
+            None => self.root = Some(Box::new(Node { term, children: HashMap::new() })),
+        }
+    }
+
+    /// Returns every indexed term within edit distance `tolerance` of `query`,
+    /// paired with its distance, pruning subtrees the triangle inequality
+    /// rules out (a child reachable only through an edge distance outside
+    /// `[k - tolerance, k + tolerance]` cannot contain a match, where `k` is
+    /// the query's distance to the parent).
+    pub fn find_within(&self, query: &str, tolerance: usize) -> Vec<(String, usize)> {
+        let mut matches = Vec::new();
+
+        if let Some(root) = &self.root {
+            root.find_within(query, tolerance, &mut matches);
+        }
+
+        matches
+    }
+}
+
+impl Node {
+    fn insert(&mut self, term: String) {
+        if term == self.term {
+            return;
+        }
+
+        let distance = levenshtein_distance(&self.term, &term);
+
+        match self.children.get_mut(&distance) {
+            Some(child) => child.insert(term),
+            None => {
+                self.children.insert(distance, Node { term, children: HashMap::new() });
+            }
+        }
+    }
+
+    fn find_within(&self, query: &str, tolerance: usize, matches: &mut Vec<(String, usize)>) {
+        let k = levenshtein_distance(&self.term, query);
+
+        if k <= tolerance {
+            matches.push((self.term.clone(), k));
+        }
+
+        let low = k.saturating_sub(tolerance);
+        let high = k + tolerance;
+        for (&edge, child) in &self.children {
+            if edge >= low && edge <= high {
+                child.find_within(query, tolerance, matches);
+            }
+        }
+    }
+}
+
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    let mut curr_row = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr_row[0] = i;
+
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr_row[j] = min(
+                min(curr_row[j - 1] + 1, prev_row[j] + 1),
+                prev_row[j - 1] + substitution_cost,
+            );
+        }
+
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    prev_row[b.len()]
+}
@@ -4,22 +4,131 @@ use std::sync::RwLock;
 use std::vec::Vec;
 use serde::{Serialize, Deserialize};
 
-#[derive(Clone, Debug)]
+use crate::bk_tree::BkTree;
+use crate::query::{self, Clause, Term};
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Query terms of up to 3 characters must match exactly; longer terms
+/// tolerate more Levenshtein edit distance, since a typo is proportionally
+/// less disruptive the longer the word is.
+fn fuzzy_tolerance(term_len: usize) -> usize {
+    match term_len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// Down-weights a fuzzy match's contribution to a document's score relative
+/// to an exact one by a factor of `1 / (distance + 1)`. This does not
+/// guarantee that exact matches rank above fuzzy ones: a fuzzy term with a
+/// higher IDF or term frequency can still outscore an exact term.
+fn fuzziness_weight(distance: usize) -> f64 {
+    1.0 / (distance as f64 + 1.0)
+}
+
+/// Finds every document containing `phrase` as consecutive tokens, returning
+/// how many times the phrase occurs in each. Only the first word needs an
+/// index lookup to find candidate documents; the rest just verify that the
+/// word after position `p` of word `i - 1` is word `i` at position `p + i`.
+fn phrase_matches(
+    index: &HashMap<String, Vec<DocReference>>,
+    positions: &HashMap<(String, usize), Vec<(usize, usize)>>,
+    phrase: &[String],
+) -> HashMap<usize, usize> {
+    let mut result = HashMap::new();
+
+    let Some(first_refs) = index.get(&phrase[0]) else {
+        return result;
+    };
+
+    'doc: for doc_ref in first_refs {
+        let doc_id = doc_ref.doc_id;
+        let Some(first_positions) = positions.get(&(phrase[0].clone(), doc_id)) else {
+            continue;
+        };
+
+        // A candidate start is (field_index, offset): carrying the field
+        // index along means the retain below can only match a later word at
+        // the same offset + i *within that field*, never across a field
+        // boundary.
+        let mut candidate_starts: HashSet<(usize, usize)> = first_positions.iter().copied().collect();
+
+        for (i, word) in phrase.iter().enumerate().skip(1) {
+            let Some(word_positions) = positions.get(&(word.clone(), doc_id)) else {
+                continue 'doc;
+            };
+
+            candidate_starts.retain(|&(field_index, start)| word_positions.contains(&(field_index, start + i)));
+            if candidate_starts.is_empty() {
+                continue 'doc;
+            }
+        }
+
+        result.insert(doc_id, candidate_starts.len());
+    }
+
+    result
+}
+
+/// Bundles the read-locked state and corpus-wide statistics a clause needs
+/// to resolve against, so `clause_doc_ids`/`clause_contributions` take one
+/// argument instead of one per piece of state.
+#[derive(Clone, Copy)]
+struct SearchContext<'a> {
+    index: &'a HashMap<String, Vec<DocReference>>,
+    terms: &'a BkTree,
+    positions: &'a HashMap<(String, usize), Vec<(usize, usize)>>,
+    doc_lengths: &'a HashMap<usize, usize>,
+    n: f64,
+    avgdl: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Document {
-    pub name: String,
-    pub content: String,
+    pub fields: HashMap<String, String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DocReference {
     pub doc_id: usize,
     pub matches: usize,
+    pub score: f64,
 }
 
 pub struct InvertedIndex {
     index: RwLock<HashMap<String, Vec<DocReference>>>,
     next_doc_id: AtomicUsize,
     documents: RwLock<HashMap<usize, Document>>,
+    doc_lengths: RwLock<HashMap<usize, usize>>,
+    total_length: AtomicUsize,
+    /// Vocabulary of every term ever indexed, queried for typo-tolerant
+    /// expansion of every query word, whether or not it also has an exact
+    /// match. Terms are never
+    /// removed from it, even once their postings are gone, since a stale
+    /// term with no postings simply contributes nothing to a search.
+    terms: RwLock<BkTree>,
+    /// Token positions within each document, keyed by (term, doc_id), so
+    /// phrase queries can check that consecutive phrase terms occupy
+    /// consecutive positions. Each position is (field_index, offset): offsets
+    /// reset to 0 at the start of every field, so adjacency can only match
+    /// within a single field and never across a field boundary. Kept
+    /// separate from `index` so the public `DocReference` shape used in
+    /// search results stays unchanged.
+    positions: RwLock<HashMap<(String, usize), Vec<(usize, usize)>>>,
+}
+
+/// A point-in-time copy of everything `InvertedIndex` needs to resume from
+/// cold, suitable for writing to disk with a compact binary serde format.
+#[derive(Serialize, Deserialize)]
+pub struct IndexSnapshot {
+    postings: HashMap<String, Vec<DocReference>>,
+    documents: HashMap<usize, Document>,
+    doc_lengths: HashMap<usize, usize>,
+    positions: HashMap<(String, usize), Vec<(usize, usize)>>,
+    next_doc_id: usize,
 }
 
 impl InvertedIndex {
@@ -28,56 +137,260 @@ impl InvertedIndex {
             index: RwLock::new(HashMap::new()),
             next_doc_id: AtomicUsize::new(0),
             documents: RwLock::new(HashMap::new()),
+            doc_lengths: RwLock::new(HashMap::new()),
+            total_length: AtomicUsize::new(0),
+            terms: RwLock::new(BkTree::new()),
+            positions: RwLock::new(HashMap::new()),
         }
     }
 
     pub fn add_document(&self, document: Document) -> usize {
         let doc_id = self.next_doc_id.fetch_add(1, atomic::Ordering::Relaxed);
 
-        let tokens = self.tokenize(&document.content);
-        let mut token_counts: HashMap<String, usize> = HashMap::new();
-        for token in tokens {
-            *token_counts.entry(token).or_default() += 1;
+        let doc_length = self.index_fields(doc_id, &document.fields);
+        self.doc_lengths.write().unwrap().insert(doc_id, doc_length);
+        self.total_length.fetch_add(doc_length, atomic::Ordering::Relaxed);
+
+        self.documents.write().unwrap().insert(doc_id, document);
+
+        doc_id
+    }
+
+    /// Re-indexes `doc_id` with `document`'s fields, replacing whatever was
+    /// previously stored for it. The document keeps its existing id so
+    /// outstanding search results and `/document?docID=` links stay valid.
+    pub fn update_document(&self, doc_id: usize, document: Document) {
+        self.remove_postings_for_doc(doc_id);
+
+        if let Some(old_length) = self.doc_lengths.write().unwrap().remove(&doc_id) {
+            self.total_length.fetch_sub(old_length, atomic::Ordering::Relaxed);
         }
 
-        {
-            let mut index = self.index.write().unwrap();
+        let doc_length = self.index_fields(doc_id, &document.fields);
+        self.doc_lengths.write().unwrap().insert(doc_id, doc_length);
+        self.total_length.fetch_add(doc_length, atomic::Ordering::Relaxed);
+
+        self.documents.write().unwrap().insert(doc_id, document);
+    }
+
+    /// Removes `doc_id` and all of its postings from the index. Returns
+    /// `false` if no such document existed.
+    pub fn remove_document(&self, doc_id: usize) -> bool {
+        if self.documents.write().unwrap().remove(&doc_id).is_none() {
+            return false;
+        }
 
-            for (token, matches) in token_counts.into_iter() {
-                index
-                    .entry(token)
-                    .or_insert_with(Vec::new)
-                    .push(DocReference { doc_id, matches });
+        self.remove_postings_for_doc(doc_id);
+
+        if let Some(length) = self.doc_lengths.write().unwrap().remove(&doc_id) {
+            self.total_length.fetch_sub(length, atomic::Ordering::Relaxed);
+        }
+
+        true
+    }
+
+    fn index_fields(&self, doc_id: usize, fields: &HashMap<String, String>) -> usize {
+        // Fields are a HashMap with no defined iteration order, so sort by
+        // name to get a deterministic field index for every token in the
+        // document (needed so phrase matching sees stable, repeatable
+        // positions). The offset itself resets at each field boundary, so a
+        // phrase can only match tokens within the same field: the last token
+        // of one field and the first token of the next never look adjacent.
+        let mut field_names: Vec<&String> = fields.keys().collect();
+        field_names.sort();
+
+        let mut doc_length = 0;
+        let mut token_positions: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        let mut bare_tokens: HashSet<String> = HashSet::new();
+        for (field_index, field) in field_names.into_iter().enumerate() {
+            let mut offset = 0;
+            for token in self.tokenize(&fields[field]) {
+                // Index both the bare token (for plain full-text search) and a
+                // field-prefixed variant so a future filtered search can target one field.
+                // Only the bare token is real vocabulary, so only it goes into `terms`:
+                // the prefixed variant is a composite nobody would type as a query word,
+                // and fuzzy-matching against it would surface spurious results.
+                token_positions.entry(token.clone()).or_default().push((field_index, offset));
+                token_positions.entry(format!("{field}:{token}")).or_default().push((field_index, offset));
+                bare_tokens.insert(token);
+                offset += 1;
+                doc_length += 1;
             }
         }
 
-        self.documents.write().unwrap().insert(doc_id, document);
+        let mut index = self.index.write().unwrap();
+        let mut terms = self.terms.write().unwrap();
+        let mut positions = self.positions.write().unwrap();
+        for (token, token_offsets) in token_positions.into_iter() {
+            if bare_tokens.contains(&token) && !index.contains_key(&token) {
+                terms.insert(token.clone());
+            }
 
-        doc_id
+            index
+                .entry(token.clone())
+                .or_default()
+                .push(DocReference { doc_id, matches: token_offsets.len(), score: 0.0 });
+
+            positions.insert((token, doc_id), token_offsets);
+        }
+
+        doc_length
+    }
+
+    /// Strips every posting and position for `doc_id` out of the index,
+    /// dropping any term whose postings list becomes empty as a result.
+    fn remove_postings_for_doc(&self, doc_id: usize) {
+        let mut index = self.index.write().unwrap();
+        index.retain(|_, references| {
+            references.retain(|doc_ref| doc_ref.doc_id != doc_id);
+            !references.is_empty()
+        });
+
+        self.positions.write().unwrap().retain(|(_, d), _| *d != doc_id);
     }
 
+    /// Evaluates a query that may combine quoted phrases, implicit/explicit
+    /// `AND`, `OR`, and leading `-` exclusions (e.g. `"inverted index" OR
+    /// bm25 -fuzzy`), falling back to plain implicit-AND over bare words
+    /// when none of that syntax is used.
     pub fn search(&self, query: &str) -> Vec<DocReference> {
+        let parsed_query = query::parse(query);
+        if parsed_query.groups.is_empty() {
+            return Vec::new();
+        }
+
         let index = self.index.read().unwrap();
+        let terms = self.terms.read().unwrap();
+        let positions = self.positions.read().unwrap();
+        let doc_lengths = self.doc_lengths.read().unwrap();
+        let n = self.document_count() as f64;
+        let avgdl = self.average_doc_length();
+        let ctx = SearchContext { index: &index, terms: &terms, positions: &positions, doc_lengths: &doc_lengths, n, avgdl };
+
+        let mut matches: HashMap<usize, usize> = HashMap::new();
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+
+        for group in &parsed_query.groups {
+            let (positive, negative): (Vec<&Clause>, Vec<&Clause>) =
+                group.iter().partition(|clause| !clause.negated);
+
+            let mut group_contributions: Option<HashMap<usize, (usize, f64)>> = None;
+            for clause in &positive {
+                let contributions = self.clause_contributions(&ctx, &clause.term);
+
+                group_contributions = Some(match group_contributions {
+                    None => contributions,
+                    Some(prev) => prev
+                        .into_iter()
+                        .filter_map(|(doc_id, (prev_matches, prev_score))| {
+                            contributions
+                                .get(&doc_id)
+                                .map(|(doc_matches, doc_score)| (doc_id, (prev_matches + doc_matches, prev_score + doc_score)))
+                        })
+                        .collect(),
+                });
+            }
+
+            let Some(mut group_contributions) = group_contributions else {
+                continue;
+            };
+
+            for clause in &negative {
+                let excluded = self.clause_doc_ids(&ctx, &clause.term);
+                group_contributions.retain(|doc_id, _| !excluded.contains(doc_id));
+            }
+
+            for (doc_id, (doc_matches, doc_score)) in group_contributions {
+                *matches.entry(doc_id).or_default() += doc_matches;
+                *scores.entry(doc_id).or_default() += doc_score;
+            }
+        }
+
+        let mut results: Vec<DocReference> = matches
+            .keys()
+            .map(|&doc_id| DocReference {
+                doc_id,
+                matches: *matches.get(&doc_id).unwrap_or(&0),
+                score: *scores.get(&doc_id).unwrap_or(&0.0),
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        results
+    }
+
+    /// Resolves a single clause's term to the set of documents it matches,
+    /// ignoring score — used to evaluate `-` exclusions.
+    fn clause_doc_ids(&self, ctx: &SearchContext, term: &Term) -> HashSet<usize> {
+        match term {
+            Term::Word(word) => ctx
+                .terms
+                .find_within(word, fuzzy_tolerance(word.chars().count()))
+                .iter()
+                .filter_map(|(matched_term, _)| ctx.index.get(matched_term))
+                .flat_map(|references| references.iter().map(|doc_ref| doc_ref.doc_id))
+                .collect(),
+            Term::Phrase(words) => phrase_matches(ctx.index, ctx.positions, words).into_keys().collect(),
+        }
+    }
+
+    /// Resolves a single clause's term to BM25-style (matches, score)
+    /// contributions per document, fuzzy-expanding bare words and requiring
+    /// consecutive positions for phrases.
+    fn clause_contributions(&self, ctx: &SearchContext, term: &Term) -> HashMap<usize, (usize, f64)> {
+        let mut contributions: HashMap<usize, (usize, f64)> = HashMap::new();
+        let SearchContext { index, terms, positions, doc_lengths, n, avgdl } = *ctx;
+
+        match term {
+            Term::Word(word) => {
+                for (matched_term, distance) in terms.find_within(word, fuzzy_tolerance(word.chars().count())) {
+                    let Some(references) = index.get(&matched_term) else {
+                        continue;
+                    };
 
-        let tokens = self.tokenize(query);
+                    let n_t = references.len() as f64;
+                    let idf = (1.0 + (n - n_t + 0.5) / (n_t + 0.5)).ln();
+                    let weight = fuzziness_weight(distance);
+
+                    for doc_ref in references {
+                        let doc_len = *doc_lengths.get(&doc_ref.doc_id).unwrap_or(&0) as f64;
+                        let f = doc_ref.matches as f64;
+                        let denom = f + K1 * (1.0 - B + B * doc_len / avgdl);
+
+                        let entry = contributions.entry(doc_ref.doc_id).or_insert((0, 0.0));
+                        entry.0 += doc_ref.matches;
+                        entry.1 += weight * idf * (f * (K1 + 1.0)) / denom;
+                    }
+                }
+            }
+            Term::Phrase(words) => {
+                // A phrase is scored like a single, highly specific term: its
+                // IDF is ln(1 + N), the value the term formula gives for a
+                // document frequency of one half, which is above the IDF of
+                // any single term that occurs in at least one document.
+                let idf = (1.0 + n).ln();
 
-        let mut results: HashSet<DocReference> = HashSet::new();
+                for (doc_id, occurrences) in phrase_matches(index, positions, words) {
+                    let doc_len = *doc_lengths.get(&doc_id).unwrap_or(&0) as f64;
+                    let f = occurrences as f64;
+                    let denom = f + K1 * (1.0 - B + B * doc_len / avgdl);
 
-        for token in tokens {
-            if let Some(references) = index.get(&token) {
-                if results.is_empty() {
-                    results = HashSet::from_iter(references.iter().cloned());
-                } else {
-                    let references: HashSet<DocReference> =
-                        HashSet::from_iter(references.iter().cloned());
-                    results.retain(|doc_ref| references.contains(doc_ref));
+                    contributions.insert(doc_id, (occurrences, idf * (f * (K1 + 1.0)) / denom));
                 }
-            } else {
-                return Vec::new();
             }
         }
 
-        results.into_iter().collect()
+        contributions
+    }
+
+    fn average_doc_length(&self) -> f64 {
+        let doc_count = self.document_count();
+        if doc_count == 0 {
+            return 0.0;
+        }
+
+        self.total_length.load(atomic::Ordering::Relaxed) as f64 / doc_count as f64
     }
 
     fn tokenize(&self, text: &str) -> Vec<String> {
@@ -89,7 +402,7 @@ impl InvertedIndex {
     }
 
     pub fn document_count(&self) -> usize {
-        self.next_doc_id.load(atomic::Ordering::Relaxed)
+        self.documents.read().unwrap().len()
     }
 
     pub fn term_count(&self) -> usize {
@@ -103,4 +416,49 @@ impl InvertedIndex {
             .get(&doc_id)
             .map(|doc_ref| doc_ref.clone())
     }
+
+    /// Captures the current postings, documents, and id counter so they can
+    /// be written to a snapshot file and restored later via [`from_snapshot`](Self::from_snapshot).
+    pub fn snapshot(&self) -> IndexSnapshot {
+        IndexSnapshot {
+            postings: self.index.read().unwrap().clone(),
+            documents: self.documents.read().unwrap().clone(),
+            doc_lengths: self.doc_lengths.read().unwrap().clone(),
+            positions: self.positions.read().unwrap().clone(),
+            next_doc_id: self.next_doc_id.load(atomic::Ordering::Relaxed),
+        }
+    }
+
+    /// Rebuilds an `InvertedIndex` from a previously captured [`IndexSnapshot`],
+    /// skipping the tokenization work a fresh `add_document` pass would require.
+    pub fn from_snapshot(snapshot: IndexSnapshot) -> Self {
+        let total_length = snapshot.doc_lengths.values().sum();
+
+        // Field-prefixed postings ("field:token") aren't real vocabulary and were
+        // never added to the BK-tree when indexed; keep them out on restore too.
+        let field_names: HashSet<&String> =
+            snapshot.documents.values().flat_map(|document| document.fields.keys()).collect();
+        let is_field_prefixed = |token: &str| {
+            field_names
+                .iter()
+                .any(|field| token.strip_prefix(field.as_str()).is_some_and(|rest| rest.starts_with(':')))
+        };
+
+        let mut terms = BkTree::new();
+        for token in snapshot.postings.keys() {
+            if !is_field_prefixed(token) {
+                terms.insert(token.clone());
+            }
+        }
+
+        InvertedIndex {
+            index: RwLock::new(snapshot.postings),
+            next_doc_id: AtomicUsize::new(snapshot.next_doc_id),
+            documents: RwLock::new(snapshot.documents),
+            doc_lengths: RwLock::new(snapshot.doc_lengths),
+            total_length: AtomicUsize::new(total_length),
+            terms: RwLock::new(terms),
+            positions: RwLock::new(snapshot.positions),
+        }
+    }
 }
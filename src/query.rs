@@ -0,0 +1,116 @@
+/// A single query term: either a bare word or a quoted phrase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Word(String),
+    Phrase(Vec<String>),
+}
+
+/// One term in a query, optionally negated with a leading `-`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clause {
+    pub term: Term,
+    pub negated: bool,
+}
+
+/// A parsed query in disjunctive normal form: each inner `Vec<Clause>` is
+/// AND'd together, and the groups themselves are OR'd. A bare multi-word
+/// query with no `OR` parses to a single AND group, matching the old
+/// implicit-AND behavior.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub groups: Vec<Vec<Clause>>,
+}
+
+pub fn parse(raw: &str) -> Query {
+    let mut groups = Vec::new();
+    let mut current_group = Vec::new();
+
+    for raw_token in tokenize(raw) {
+        match raw_token {
+            RawToken::Or => {
+                if !current_group.is_empty() {
+                    groups.push(std::mem::take(&mut current_group));
+                }
+            }
+            RawToken::And => {}
+            RawToken::Clause(clause) => current_group.push(clause),
+        }
+    }
+
+    if !current_group.is_empty() {
+        groups.push(current_group);
+    }
+
+    Query { groups }
+}
+
+enum RawToken {
+    Or,
+    And,
+    Clause(Clause),
+}
+
+fn tokenize(raw: &str) -> Vec<RawToken> {
+    let mut tokens = Vec::new();
+    let mut chars = raw.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let negated = c == '-';
+        if negated {
+            chars.next();
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+
+            let mut phrase_text = String::new();
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                phrase_text.push(c);
+            }
+
+            let words: Vec<String> = phrase_text.split_whitespace().map(normalize_word).filter(|w| !w.is_empty()).collect();
+            if !words.is_empty() {
+                tokens.push(RawToken::Clause(Clause { term: Term::Phrase(words), negated }));
+            }
+            continue;
+        }
+
+        let mut raw_word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            raw_word.push(c);
+            chars.next();
+        }
+
+        match raw_word.as_str() {
+            "OR" if !negated => tokens.push(RawToken::Or),
+            "AND" if !negated => tokens.push(RawToken::And),
+            _ => {
+                let word = normalize_word(&raw_word);
+                if !word.is_empty() {
+                    tokens.push(RawToken::Clause(Clause { term: Term::Word(word), negated }));
+                }
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Matches `InvertedIndex::tokenize`'s normalization so a query word looks
+/// up the same token that was indexed.
+fn normalize_word(word: &str) -> String {
+    word.to_lowercase()
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_string()
+}
@@ -1,21 +1,43 @@
 use std::{fs, thread};
 use std::io::{self, prelude::*, BufReader};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 use serde::{Serialize, Deserialize};
 use serde_json;
 
-use crate::inverted_index::{Document, DocReference, InvertedIndex};
+use crate::inverted_index::{Document, DocReference, IndexSnapshot, InvertedIndex};
+use crate::task::{Task, TaskKind, TaskRegistry};
 use crate::thread_pool::ThreadPool;
 
+/// Maps a corpus file to the (possibly multiple, for CSV/JSONL) document ids
+/// it produced, so a later modification or deletion can retire exactly those ids.
+type PathIndex = RwLock<HashMap<PathBuf, Vec<usize>>>;
+
+/// Tracks the modification time the index was last synced to for each corpus
+/// file, so a restart can skip re-tokenizing files that haven't changed.
+type FileMtimes = RwLock<HashMap<PathBuf, SystemTime>>;
+
+const SNAPSHOT_FILE_NAME: &str = ".snapshot";
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Everything a connection handler or background task needs to serve a
+/// request, bundled so it can be cheaply cloned into a thread-pool closure.
+#[derive(Clone)]
+struct ServerState {
+    index: Arc<InvertedIndex>,
+    tasks: Arc<TaskRegistry>,
+    paths: Arc<PathIndex>,
+    file_mtimes: Arc<FileMtimes>,
+    corpus_dir: Arc<PathBuf>,
+}
+
 pub struct SearchServer {
     listener: TcpListener,
     pool: ThreadPool,
-    index: Arc<InvertedIndex>,
-    corpus_dir: PathBuf,
+    state: ServerState,
 }
 
 impl SearchServer {
@@ -24,33 +46,66 @@ impl SearchServer {
             TcpListener::bind((ip, port)).expect("Failed to bind to specified address and port");
 
         let pool = ThreadPool::new(thread_num);
-        let index = Arc::new(InvertedIndex::new());
 
-        let mut server = Self {
-            listener,
-            pool,
-            index,
-            corpus_dir,
+        let (index, paths, file_mtimes) = match load_snapshot(&corpus_dir) {
+            Some(snapshot) => {
+                println!("Restored index from snapshot");
+                (InvertedIndex::from_snapshot(snapshot.index), snapshot.paths, snapshot.file_mtimes)
+            }
+            None => (InvertedIndex::new(), HashMap::new(), HashMap::new()),
         };
 
+        let state = ServerState {
+            index: Arc::new(index),
+            tasks: Arc::new(TaskRegistry::new()),
+            paths: Arc::new(RwLock::new(paths)),
+            file_mtimes: Arc::new(RwLock::new(file_mtimes)),
+            corpus_dir: Arc::new(corpus_dir),
+        };
+
+        let mut server = Self { listener, pool, state };
+
         server.index_initial_corpus()?;
 
         server.setup_directory_watcher();
+        server.setup_periodic_snapshot();
 
         Ok(server)
     }
 
     fn index_initial_corpus(&mut self) -> io::Result<()> {
-        let corpus = txt_files_in_dir(&self.corpus_dir)?;
+        let corpus = corpus_files_in_dir(&self.state.corpus_dir)?;
+        let corpus_paths: HashSet<&PathBuf> = corpus.iter().collect();
+
+        let stale_paths: Vec<PathBuf> = self
+            .state
+            .paths
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|path| !corpus_paths.contains(path))
+            .cloned()
+            .collect();
+
+        for path in stale_paths {
+            println!("Removing stale snapshot entry for {}", path.to_string_lossy());
+            remove_file_from_index(&path, &self.state.index, &self.state.paths, &self.state.file_mtimes);
+        }
+
+        for path in corpus {
+            let already_current = self.state.paths.read().unwrap().contains_key(&path)
+                && path.metadata().and_then(|meta| meta.modified()).ok()
+                    == self.state.file_mtimes.read().unwrap().get(&path).copied();
+
+            if already_current {
+                println!("Skipping unchanged file: {}", path.to_string_lossy());
+                continue;
+            }
+
+            let state = self.state.clone();
 
-        for path in corpus.clone() {
-            let index = Arc::clone(&self.index);
             self.pool.execute(move || {
-                if let Err(e) = add_file_to_index(path.as_path(), &index) {
-                    eprintln!("Error processing file {}: {}", path.to_string_lossy(), e);
-                } else {
-                    println!("Indexed file: {}", path.to_string_lossy());
-                }
+                index_file(path.as_path(), &state.index, &state.tasks, &state.paths, &state.file_mtimes);
             });
         }
 
@@ -58,59 +113,64 @@ impl SearchServer {
 
         println!(
             "Indexing complete. Total documents: {}",
-            self.index.document_count()
+            self.state.index.document_count()
         );
-        println!("Unique terms: {}", self.index.term_count());
+        println!("Unique terms: {}", self.state.index.term_count());
 
         Ok(())
     }
 
+    // Runs on a dedicated OS thread rather than `self.pool.execute` since
+    // `watch_directory` never returns: submitting it to the pool would
+    // permanently tie up one of its fixed worker slots.
     fn setup_directory_watcher(&self) {
-        let corpus_dir = self.corpus_dir.clone();
-        let index = Arc::clone(&self.index);
+        let state = self.state.clone();
 
-        self.pool.execute(move || {
-            let corpus = txt_files_in_dir(&corpus_dir).unwrap_or_default();
+        thread::spawn(move || {
+            let corpus = corpus_files_in_dir(&state.corpus_dir).unwrap_or_default();
 
             watch_directory(
-                &corpus_dir,
+                &state.corpus_dir,
                 corpus,
                 Duration::from_secs(1),
                 |path| {
                     println!("New file {} detected", path.to_string_lossy());
-                    if let Err(e) = add_file_to_index(path, &index) {
-                        eprintln!(
-                            "Error processing new file {}: {}",
-                            path.to_string_lossy(),
-                            e
-                        );
-                    } else {
-                        println!("Indexed file: {}", path.to_string_lossy());
-                    }
+                    index_file(path, &state.index, &state.tasks, &state.paths, &state.file_mtimes);
                 },
                 |path| {
                     println!("Modified file {} detected", path.to_string_lossy());
-                    if let Err(e) = add_file_to_index(path, &index) {
-                        eprintln!(
-                            "Error processing modified file {}: {}",
-                            path.to_string_lossy(),
-                            e
-                        );
-                    } else {
-                        println!("Indexed file: {}", path.to_string_lossy());
-                    }
+                    index_file(path, &state.index, &state.tasks, &state.paths, &state.file_mtimes);
+                },
+                |path| {
+                    println!("Removed file {} detected", path.to_string_lossy());
+                    remove_file_from_index(path, &state.index, &state.paths, &state.file_mtimes);
                 },
             );
         });
     }
 
+    // Same reasoning as `setup_directory_watcher`: this loop never returns,
+    // so it runs on its own thread instead of occupying a pool worker that
+    // `run` needs for handling connections.
+    fn setup_periodic_snapshot(&self) {
+        let state = self.state.clone();
+
+        thread::spawn(move || loop {
+            thread::sleep(SNAPSHOT_INTERVAL);
+
+            if let Err(e) = save_snapshot(&state.corpus_dir, &state.index, &state.paths, &state.file_mtimes) {
+                eprintln!("Failed to save index snapshot: {}", e);
+            }
+        });
+    }
+
     pub fn run(&self) -> io::Result<()> {
         for stream in self.listener.incoming() {
             let stream = stream?;
-            let index = Arc::clone(&self.index);
+            let state = self.state.clone();
 
             self.pool.execute(move || {
-                handle_connection(stream, &index);
+                handle_connection(stream, &state);
             });
         }
 
@@ -118,23 +178,78 @@ impl SearchServer {
     }
 }
 
-fn txt_files_in_dir(dir_path: &Path) -> io::Result<Vec<PathBuf>> {
+impl Drop for SearchServer {
+    fn drop(&mut self) {
+        if let Err(e) = save_snapshot(
+            &self.state.corpus_dir,
+            &self.state.index,
+            &self.state.paths,
+            &self.state.file_mtimes,
+        ) {
+            eprintln!("Failed to save index snapshot on shutdown: {}", e);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ServerSnapshot {
+    index: IndexSnapshot,
+    paths: HashMap<PathBuf, Vec<usize>>,
+    file_mtimes: HashMap<PathBuf, SystemTime>,
+}
+
+fn snapshot_path(corpus_dir: &Path) -> PathBuf {
+    corpus_dir.join(SNAPSHOT_FILE_NAME)
+}
+
+fn save_snapshot(
+    corpus_dir: &Path,
+    index: &InvertedIndex,
+    paths: &PathIndex,
+    file_mtimes: &FileMtimes,
+) -> io::Result<()> {
+    let snapshot = ServerSnapshot {
+        index: index.snapshot(),
+        paths: paths.read().unwrap().clone(),
+        file_mtimes: file_mtimes.read().unwrap().clone(),
+    };
+
+    let bytes = bincode::serialize(&snapshot).map_err(io::Error::other)?;
+    fs::write(snapshot_path(corpus_dir), bytes)
+}
+
+fn load_snapshot(corpus_dir: &Path) -> Option<ServerSnapshot> {
+    let bytes = fs::read(snapshot_path(corpus_dir)).ok()?;
+    bincode::deserialize(&bytes).ok()
+}
+
+const SUPPORTED_EXTENSIONS: [&str; 4] = ["txt", "csv", "jsonl", "json"];
+
+fn corpus_files_in_dir(dir_path: &Path) -> io::Result<Vec<PathBuf>> {
     Ok(fs::read_dir(dir_path)?
         .filter_map(|entry| entry.ok())
         .map(|entry| entry.path())
-        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "txt"))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext))
+        })
         .collect::<Vec<_>>())
 }
 
-fn watch_directory<F, G>(
+fn watch_directory<F, G, H>(
     dir_path: &Path,
     present_files: Vec<PathBuf>,
     interval: Duration,
     on_new_file: F,
     on_modified_file: G,
+    on_removed_file: H,
 ) where
     F: Fn(&Path),
     G: Fn(&Path),
+    H: Fn(&Path),
 {
     let mut present_files = present_files
         .into_iter()
@@ -147,7 +262,20 @@ fn watch_directory<F, G>(
         .collect::<HashMap<PathBuf, SystemTime>>();
 
     loop {
-        if let Ok(new_paths) = txt_files_in_dir(dir_path) {
+        if let Ok(new_paths) = corpus_files_in_dir(dir_path) {
+            let current_paths: HashSet<&PathBuf> = new_paths.iter().collect();
+
+            let removed_paths: Vec<PathBuf> = present_files
+                .keys()
+                .filter(|path| !current_paths.contains(path))
+                .cloned()
+                .collect();
+
+            for path in removed_paths {
+                on_removed_file(&path);
+                present_files.remove(&path);
+            }
+
             for path in new_paths {
                 let modified = path.metadata().map_or(SystemTime::now(), |meta| {
                     meta.modified().unwrap_or(SystemTime::now())
@@ -171,18 +299,206 @@ fn watch_directory<F, G>(
     }
 }
 
-fn add_file_to_index(path: &Path, index: &InvertedIndex) -> io::Result<()> {
+/// Indexes `path` for the first time or re-syncs it against whatever ids it
+/// previously produced, reusing ids where rows still line up so existing
+/// search results and `/document?docID=` links keep working.
+fn index_file(
+    path: &Path,
+    index: &InvertedIndex,
+    tasks: &TaskRegistry,
+    paths: &PathIndex,
+    file_mtimes: &FileMtimes,
+) {
+    let uid = tasks.enqueue(TaskKind::DocumentAddition);
+    tasks.start(uid);
+
+    match parse_documents(path) {
+        Ok(documents) => {
+            let previous_ids = paths.write().unwrap().remove(path).unwrap_or_default();
+            let mut previous_ids = previous_ids.into_iter();
+
+            let mut doc_ids = Vec::with_capacity(documents.len());
+            for document in documents {
+                match previous_ids.next() {
+                    Some(doc_id) => {
+                        index.update_document(doc_id, document);
+                        doc_ids.push(doc_id);
+                    }
+                    None => doc_ids.push(index.add_document(document)),
+                }
+            }
+
+            // The file now has fewer documents than before (e.g. a shrunk CSV);
+            // drop the postings for the ids that no longer correspond to a row.
+            for stale_id in previous_ids {
+                index.remove_document(stale_id);
+            }
+
+            paths.write().unwrap().insert(path.to_path_buf(), doc_ids);
+
+            if let Ok(modified) = path.metadata().and_then(|meta| meta.modified()) {
+                file_mtimes.write().unwrap().insert(path.to_path_buf(), modified);
+            }
+
+            tasks.succeed(uid);
+            println!("Indexed file: {}", path.to_string_lossy());
+        }
+        Err(e) => {
+            tasks.fail(uid, e.to_string());
+            eprintln!("Error processing file {}: {}", path.to_string_lossy(), e);
+        }
+    }
+}
+
+fn remove_file_from_index(path: &Path, index: &InvertedIndex, paths: &PathIndex, file_mtimes: &FileMtimes) {
+    if let Some(doc_ids) = paths.write().unwrap().remove(path) {
+        for doc_id in doc_ids {
+            index.remove_document(doc_id);
+        }
+    }
+
+    file_mtimes.write().unwrap().remove(path);
+}
+
+fn parse_documents(path: &Path) -> io::Result<Vec<Document>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => parse_csv_documents(path),
+        Some("jsonl") => parse_jsonl_documents(path),
+        Some("json") => parse_json_documents(path),
+        _ => parse_txt_document(path),
+    }
+}
+
+fn parse_txt_document(path: &Path) -> io::Result<Vec<Document>> {
     let mut file = fs::File::open(path)?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
 
-    let document = Document {
-        name: path.file_name().unwrap().to_string_lossy().into_owned(),
-        content,
+    let mut fields = HashMap::new();
+    fields.insert("name".to_string(), path.file_name().unwrap().to_string_lossy().into_owned());
+    fields.insert("content".to_string(), content);
+
+    Ok(vec![Document { fields }])
+}
+
+// Splits are done line-by-line, so a `"`-quoted field that itself contains a
+// newline still misaligns the row after it: the file is split into lines
+// before quoting is considered at all. Only commas and doubled `""` quotes
+// inside a quoted field are handled correctly.
+fn parse_csv_documents(path: &Path) -> io::Result<Vec<Document>> {
+    let content = fs::read_to_string(path)?;
+    let mut lines = content.lines();
+
+    let Some(header_line) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let headers: Vec<String> = split_csv_line(header_line).into_iter().map(|header| header.trim().to_string()).collect();
+
+    let documents = lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let values = split_csv_line(line);
+            let fields = headers
+                .iter()
+                .enumerate()
+                .map(|(i, header)| {
+                    let value = values.get(i).map(|value| value.trim()).unwrap_or("");
+                    (header.clone(), value.to_string())
+                })
+                .collect();
+
+            Document { fields }
+        })
+        .collect();
+
+    Ok(documents)
+}
+
+/// Splits a single CSV line on commas, treating a `"`-quoted field as a unit so
+/// commas inside it don't start a new column; a doubled `""` inside a quoted
+/// field is unescaped to a literal `"`.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+fn parse_jsonl_documents(path: &Path) -> io::Result<Vec<Document>> {
+    let content = fs::read_to_string(path)?;
+
+    let documents = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .map(document_from_json_value)
+        .collect();
+
+    Ok(documents)
+}
+
+fn parse_json_documents(path: &Path) -> io::Result<Vec<Document>> {
+    let content = fs::read_to_string(path)?;
+    let value: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let documents = match value {
+        serde_json::Value::Array(items) => items.into_iter().map(document_from_json_value).collect(),
+        other => vec![document_from_json_value(other)],
     };
 
-    index.add_document(document);
-    Ok(())
+    Ok(documents)
+}
+
+fn document_from_json_value(value: serde_json::Value) -> Document {
+    let fields = match value {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let text = match value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                (key, text)
+            })
+            .collect(),
+        other => {
+            let mut fields = HashMap::new();
+            fields.insert("content".to_string(), other.to_string());
+            fields
+        }
+    };
+
+    Document { fields }
+}
+
+fn submit_document(name: String, content: String, index: &InvertedIndex, tasks: &TaskRegistry) -> usize {
+    let uid = tasks.enqueue(TaskKind::DocumentAddition);
+    tasks.start(uid);
+
+    let mut fields = HashMap::new();
+    fields.insert("name".to_string(), name);
+    fields.insert("content".to_string(), content);
+
+    index.add_document(Document { fields });
+    tasks.succeed(uid);
+
+    uid
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -190,6 +506,7 @@ enum HttpStatus {
     Ok,
     BadRequest,
     NotFound,
+    InternalServerError,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -198,7 +515,12 @@ enum Response {
     Help(WelcomeResponse),
     Search(SearchResponse),
     Document(DocumentResponse),
-    Error(ErrorResponse),
+    Submit(SubmitResponse),
+    Tasks(TasksResponse),
+    Task(Task),
+    Delete(DeleteResponse),
+    Snapshot(SnapshotResponse),
+    Error(ResponseError),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -217,16 +539,96 @@ struct SearchResponse {
 #[derive(Serialize, Deserialize, Debug)]
 struct DocumentResponse {
     document_id: usize,
-    filename: String,
-    content: String,
+    fields: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SubmitResponse {
+    uid: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct ErrorResponse {
-    error: String,
+struct TasksResponse {
+    tasks: Vec<Task>,
 }
 
-fn handle_connection(mut stream: TcpStream, index: &InvertedIndex) {
+#[derive(Serialize, Deserialize, Debug)]
+struct DeleteResponse {
+    document_id: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SnapshotResponse {
+    documents_saved: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ErrorType {
+    InvalidRequest,
+    Internal,
+    NotFound,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ErrorCode {
+    InvalidRequest,
+    InvalidRequestMethod,
+    RouteNotFound,
+    InvalidSearchQuery,
+    MissingQuery,
+    InvalidDocumentId,
+    DocumentNotFound,
+    MissingDocumentFields,
+    InvalidTaskUid,
+    TaskNotFound,
+    SnapshotSaveFailed,
+}
+
+impl ErrorCode {
+    fn err_code(self) -> (&'static str, ErrorType, HttpStatus) {
+        match self {
+            ErrorCode::InvalidRequest => ("invalid_request", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::InvalidRequestMethod => ("invalid_request_method", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::RouteNotFound => ("route_not_found", ErrorType::NotFound, HttpStatus::NotFound),
+            ErrorCode::InvalidSearchQuery => ("invalid_search_query", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::MissingQuery => ("missing_query", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::InvalidDocumentId => ("invalid_document_id", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::DocumentNotFound => ("document_not_found", ErrorType::NotFound, HttpStatus::NotFound),
+            ErrorCode::MissingDocumentFields => ("missing_document_fields", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::InvalidTaskUid => ("invalid_task_uid", ErrorType::InvalidRequest, HttpStatus::BadRequest),
+            ErrorCode::TaskNotFound => ("task_not_found", ErrorType::NotFound, HttpStatus::NotFound),
+            ErrorCode::SnapshotSaveFailed => ("snapshot_save_failed", ErrorType::Internal, HttpStatus::InternalServerError),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ResponseError {
+    message: String,
+    code: String,
+    #[serde(rename = "type")]
+    error_type: ErrorType,
+    link: String,
+}
+
+impl ResponseError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> (HttpStatus, Self) {
+        let (code_str, error_type, status) = code.err_code();
+
+        (
+            status,
+            ResponseError {
+                message: message.into(),
+                code: code_str.to_string(),
+                error_type,
+                link: format!("https://docs.search-engine.dev/errors/{code_str}"),
+            },
+        )
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, state: &ServerState) {
     let buf_reader = BufReader::new(&stream);
     let request_line = match buf_reader.lines().next() {
         Some(Ok(line)) => line,
@@ -240,11 +642,12 @@ fn handle_connection(mut stream: TcpStream, index: &InvertedIndex) {
         }
     };
 
-    let (status, response) = process_request(&request_line, index);
+    let (status, response) = process_request(&request_line, state);
     let status_line = match status {
         HttpStatus::Ok => "HTTP/1.1 200 OK",
         HttpStatus::BadRequest => "HTTP/1.1 400 BAD REQUEST",
         HttpStatus::NotFound => "HTTP/1.1 404 NOT FOUND",
+        HttpStatus::InternalServerError => "HTTP/1.1 500 INTERNAL SERVER ERROR",
     };
 
     let json_contents = serde_json::to_string(&response).unwrap();
@@ -257,18 +660,20 @@ fn handle_connection(mut stream: TcpStream, index: &InvertedIndex) {
     }
 }
 
-fn process_request(request_line: &str, index: &InvertedIndex) -> (HttpStatus, Response) {
+fn process_request(request_line: &str, state: &ServerState) -> (HttpStatus, Response) {
     let parts: Vec<&str> = request_line.split_whitespace().collect();
 
     if parts.len() < 3 {
-        return (HttpStatus::BadRequest, Response::Error(ErrorResponse { error: "Invalid Request".to_string() }));
+        let (status, error) = ResponseError::new(ErrorCode::InvalidRequest, "Malformed request line");
+        return (status, Response::Error(error));
     }
 
     let method = parts[0];
     let uri = parts[1];
 
     if method != "GET" {
-        return (HttpStatus::BadRequest, Response::Error(ErrorResponse { error: "Invalid Request Method".to_string() }));
+        let (status, error) = ResponseError::new(ErrorCode::InvalidRequestMethod, "Only GET requests are supported");
+        return (status, Response::Error(error));
     }
 
     match uri {
@@ -276,16 +681,37 @@ fn process_request(request_line: &str, index: &InvertedIndex) -> (HttpStatus, Re
             HttpStatus::Ok,
             Response::Help(WelcomeResponse {
                 message: "Welcome to the Inverted Index Search Server".to_string(),
-                endpoints: vec!["/search?q=<query>".to_string(), "/document?docID=<id>".to_string()],
+                endpoints: vec![
+                    "/search?q=<query>".to_string(),
+                    "/document?docID=<id>".to_string(),
+                    "/submit?name=<name>&content=<content>".to_string(),
+                    "/delete?docID=<id>".to_string(),
+                    "/tasks".to_string(),
+                    "/tasks?uid=<uid>".to_string(),
+                    "/snapshot".to_string(),
+                ],
             })
         ),
-        query if query.starts_with("/search?q=") => handle_search_request(&query[10..], index),
-        query if query.starts_with("/document?docID=") => handle_document_request(&query[16..], index),
-        _ => (HttpStatus::NotFound, Response::Error(ErrorResponse { error: "404 Not Found".to_string() })),
+        query if query.starts_with("/search?q=") => handle_search_request(&query[10..], &state.index),
+        query if query.starts_with("/document?docID=") => handle_document_request(&query[16..], &state.index),
+        query if query.starts_with("/submit?") => handle_submit_request(&query[8..], &state.index, &state.tasks),
+        query if query.starts_with("/delete?docID=") => handle_delete_request(&query[14..], &state.index),
+        "/tasks" => handle_tasks_request(None, &state.tasks),
+        query if query.starts_with("/tasks?uid=") => handle_tasks_request(Some(&query[11..]), &state.tasks),
+        "/snapshot" => handle_snapshot_request(state),
+        _ => {
+            let (status, error) = ResponseError::new(ErrorCode::RouteNotFound, "No such endpoint");
+            (status, Response::Error(error))
+        }
     }
 }
 
 fn handle_search_request(query: &str, index: &InvertedIndex) -> (HttpStatus, Response) {
+    if query.is_empty() {
+        let (status, error) = ResponseError::new(ErrorCode::MissingQuery, "Search query must not be empty");
+        return (status, Response::Error(error));
+    }
+
     match urlencoding::decode(query) {
         Ok(term) => {
             let results = index.search(&term);
@@ -296,9 +722,10 @@ fn handle_search_request(query: &str, index: &InvertedIndex) -> (HttpStatus, Res
                 results,
             }))
         },
-        Err(_) => (HttpStatus::BadRequest, Response::Error(ErrorResponse {
-            error: "Invalid Search Query".to_string(),
-        })),
+        Err(_) => {
+            let (status, error) = ResponseError::new(ErrorCode::InvalidSearchQuery, "Search query is not valid URL-encoded text");
+            (status, Response::Error(error))
+        }
     }
 }
 
@@ -311,17 +738,103 @@ fn handle_document_request(query: &str, index: &InvertedIndex) -> (HttpStatus, R
             if let Some(document) = index.get_document(doc_id) {
                 (HttpStatus::Ok, Response::Document(DocumentResponse {
                     document_id: doc_id,
-                    filename: document.name,
-                    content: document.content,
+                    fields: document.fields,
                 }))
             } else {
-                (HttpStatus::Ok, Response::Error(ErrorResponse {
-                    error: "No file with specified docID was found".to_string(),
-                }))
+                let (status, error) = ResponseError::new(ErrorCode::DocumentNotFound, "No file with the specified docID was found");
+                (status, Response::Error(error))
+            }
+        },
+        Err(_) => {
+            let (status, error) = ResponseError::new(ErrorCode::InvalidDocumentId, "docID must be a valid unsigned integer");
+            (status, Response::Error(error))
+        }
+    }
+}
+
+fn handle_delete_request(query: &str, index: &InvertedIndex) -> (HttpStatus, Response) {
+    match urlencoding::decode(query)
+        .map_err(|_| ())
+        .and_then(|arg| arg.parse::<usize>().map_err(|_| ()))
+    {
+        Ok(doc_id) => {
+            if index.remove_document(doc_id) {
+                (HttpStatus::Ok, Response::Delete(DeleteResponse { document_id: doc_id }))
+            } else {
+                let (status, error) = ResponseError::new(ErrorCode::DocumentNotFound, "No file with the specified docID was found");
+                (status, Response::Error(error))
+            }
+        },
+        Err(_) => {
+            let (status, error) = ResponseError::new(ErrorCode::InvalidDocumentId, "docID must be a valid unsigned integer");
+            (status, Response::Error(error))
+        }
+    }
+}
+
+fn handle_submit_request(params: &str, index: &InvertedIndex, tasks: &TaskRegistry) -> (HttpStatus, Response) {
+    let mut name = None;
+    let mut content = None;
+
+    for pair in params.split('&') {
+        let mut kv = pair.splitn(2, '=');
+        let key = kv.next().unwrap_or_default();
+        let value = kv.next().unwrap_or_default();
+
+        match key {
+            "name" => name = urlencoding::decode(value).ok().map(|s| s.into_owned()),
+            "content" => content = urlencoding::decode(value).ok().map(|s| s.into_owned()),
+            _ => {}
+        }
+    }
+
+    match (name, content) {
+        (Some(name), Some(content)) => {
+            let uid = submit_document(name, content, index, tasks);
+            (HttpStatus::Ok, Response::Submit(SubmitResponse { uid }))
+        }
+        _ => {
+            let (status, error) = ResponseError::new(
+                ErrorCode::MissingDocumentFields,
+                "Both name and content query parameters are required",
+            );
+            (status, Response::Error(error))
+        }
+    }
+}
+
+fn handle_snapshot_request(state: &ServerState) -> (HttpStatus, Response) {
+    match save_snapshot(&state.corpus_dir, &state.index, &state.paths, &state.file_mtimes) {
+        Ok(()) => (
+            HttpStatus::Ok,
+            Response::Snapshot(SnapshotResponse { documents_saved: state.index.document_count() }),
+        ),
+        Err(e) => {
+            let (status, error) = ResponseError::new(ErrorCode::SnapshotSaveFailed, e.to_string());
+            (status, Response::Error(error))
+        }
+    }
+}
+
+fn handle_tasks_request(uid_query: Option<&str>, tasks: &TaskRegistry) -> (HttpStatus, Response) {
+    let Some(raw_uid) = uid_query else {
+        return (HttpStatus::Ok, Response::Tasks(TasksResponse { tasks: tasks.list() }));
+    };
+
+    match urlencoding::decode(raw_uid)
+        .map_err(|_| ())
+        .and_then(|arg| arg.parse::<usize>().map_err(|_| ()))
+    {
+        Ok(uid) => match tasks.get(uid) {
+            Some(task) => (HttpStatus::Ok, Response::Task(task)),
+            None => {
+                let (status, error) = ResponseError::new(ErrorCode::TaskNotFound, "No task with the specified uid was found");
+                (status, Response::Error(error))
             }
         },
-        Err(_) => (HttpStatus::BadRequest, Response::Error(ErrorResponse {
-            error: "Invalid Document ID".to_string(),
-        })),
+        Err(_) => {
+            let (status, error) = ResponseError::new(ErrorCode::InvalidTaskUid, "uid must be a valid unsigned integer");
+            (status, Response::Error(error))
+        }
     }
 }
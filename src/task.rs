@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::RwLock;
+use std::time::SystemTime;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    DocumentAddition,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum TaskStatus {
+    Enqueued,
+    Processing,
+    Succeeded,
+    Failed { error: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Task {
+    pub uid: usize,
+    pub kind: TaskKind,
+    pub status: TaskStatus,
+    pub enqueued_at: SystemTime,
+    pub started_at: Option<SystemTime>,
+    pub finished_at: Option<SystemTime>,
+}
+
+/// Tracks the lifecycle of indexing work so clients can poll for completion
+/// instead of relying on server-side logging.
+pub struct TaskRegistry {
+    tasks: RwLock<HashMap<usize, Task>>,
+    next_uid: AtomicUsize,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        TaskRegistry {
+            tasks: RwLock::new(HashMap::new()),
+            next_uid: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn enqueue(&self, kind: TaskKind) -> usize {
+        let uid = self.next_uid.fetch_add(1, atomic::Ordering::Relaxed);
+
+        let task = Task {
+            uid,
+            kind,
+            status: TaskStatus::Enqueued,
+            enqueued_at: SystemTime::now(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        self.tasks.write().unwrap().insert(uid, task);
+
+        uid
+    }
+
+    pub fn start(&self, uid: usize) {
+        if let Some(task) = self.tasks.write().unwrap().get_mut(&uid) {
+            task.status = TaskStatus::Processing;
+            task.started_at = Some(SystemTime::now());
+        }
+    }
+
+    pub fn succeed(&self, uid: usize) {
+        if let Some(task) = self.tasks.write().unwrap().get_mut(&uid) {
+            task.status = TaskStatus::Succeeded;
+            task.finished_at = Some(SystemTime::now());
+        }
+    }
+
+    pub fn fail(&self, uid: usize, error: String) {
+        if let Some(task) = self.tasks.write().unwrap().get_mut(&uid) {
+            task.status = TaskStatus::Failed { error };
+            task.finished_at = Some(SystemTime::now());
+        }
+    }
+
+    pub fn get(&self, uid: usize) -> Option<Task> {
+        self.tasks.read().unwrap().get(&uid).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Task> {
+        self.tasks.read().unwrap().values().cloned().collect()
+    }
+}